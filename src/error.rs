@@ -0,0 +1,147 @@
+//! Context-carrying errors for reading and manipulating sample files.
+//!
+//! [`TrackerError`] wraps an underlying cause and attaches the sample index,
+//! field, and byte offset at which a failure occurred, so a corrupt file
+//! yields a message like `sample 42: invalid utf-8 sequence of 1 bytes from
+//! index 0 in mineral name at offset 1337` instead of a bare `failed to fill
+//! whole buffer`.
+
+use std::fmt::{Display, Formatter};
+
+/// A field of a sample, used to locate where a read failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+	/// The four-byte mod discriminator.
+	SourceMod,
+
+	/// The list's leading sample count.
+	Count,
+
+	/// The dimension ID.
+	Dimension,
+
+	/// The chunk X coordinate.
+	X,
+
+	/// The chunk Z coordinate.
+	Z,
+
+	/// The Immersive mineral name.
+	Mineral,
+
+	/// The Immersive liquid name.
+	Liquid,
+
+	/// The TerraFirmaCraft or Geolosys ore name.
+	Ore,
+
+	/// The Immersive timestamp.
+	Timestamp,
+}
+
+impl Field {
+	/// Returns a human-readable description of the field.
+	fn describe(self) -> &'static str {
+		match self {
+			Self::SourceMod => "sample type",
+			Self::Count => "sample count",
+			Self::Dimension => "dimension",
+			Self::X => "X coordinate",
+			Self::Z => "Z coordinate",
+			Self::Mineral => "mineral name",
+			Self::Liquid => "liquid name",
+			Self::Ore => "ore name",
+			Self::Timestamp => "timestamp",
+		}
+	}
+}
+
+impl Display for Field {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+		f.write_str(self.describe())
+	}
+}
+
+/// An error produced while operating on a sample file.
+pub enum TrackerError {
+	/// A failure reading a specific field, annotated with its location.
+	Read {
+		/// The index of the sample being read, or `None` for the list header.
+		sample: Option<usize>,
+
+		/// The field being read when the failure occurred.
+		field: Field,
+
+		/// The byte offset within the file at which the field began.
+		offset: u64,
+
+		/// The underlying cause.
+		source: std::io::Error,
+	},
+
+	/// An I/O error not associated with a specific field.
+	Io(std::io::Error),
+
+	/// A command-level error with a ready-made message.
+	Message(String),
+}
+
+impl TrackerError {
+	/// Constructs a [`TrackerError::Read`] annotating a field failure.
+	pub fn read(sample: Option<usize>, field: Field, offset: u64, source: std::io::Error) -> Self {
+		Self::Read {
+			sample,
+			field,
+			offset,
+			source,
+		}
+	}
+}
+
+impl Display for TrackerError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+		match self {
+			Self::Read {
+				sample: Some(sample),
+				field,
+				offset,
+				source,
+			} => write!(
+				f,
+				"sample {}: {} in {} at offset {}",
+				sample, source, field, offset
+			),
+			Self::Read {
+				sample: None,
+				field,
+				offset,
+				source,
+			} => write!(f, "{} in {} at offset {}", source, field, offset),
+			Self::Io(source) => write!(f, "{}", source),
+			Self::Message(message) => f.write_str(message),
+		}
+	}
+}
+
+// Render the human-readable chain for `main`'s `Termination`, rather than the
+// derived structural dump.
+impl std::fmt::Debug for TrackerError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+		Display::fmt(self, f)
+	}
+}
+
+impl std::error::Error for TrackerError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Read { source, .. } | Self::Io(source) => Some(source),
+			Self::Message(_) => None,
+		}
+	}
+}
+
+impl From<std::io::Error> for TrackerError {
+	fn from(source: std::io::Error) -> Self {
+		Self::Io(source)
+	}
+}
@@ -0,0 +1,444 @@
+//! A small boolean query language for selecting samples.
+//!
+//! An expression is lexed into [`Token`]s, parsed into an [`Expr`] AST with
+//! the usual `NOT` > `AND` > `OR` precedence, then evaluated against each
+//! [`Sample`].
+
+use crate::{Sample, SampleData};
+use std::fmt::{Display, Formatter};
+
+/// An error encountered while lexing or parsing a filter expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FilterError {
+	/// The byte offset within the expression at which the error occurred.
+	pub offset: usize,
+
+	/// A human-readable description of the problem.
+	pub message: String,
+}
+
+impl Display for FilterError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+		write!(f, "{} at offset {}", self.message, self.offset)
+	}
+}
+
+impl std::error::Error for FilterError {}
+
+/// A comparison operator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompareOp {
+	/// `=`
+	Eq,
+
+	/// `!=`
+	Ne,
+
+	/// `<`
+	Lt,
+
+	/// `<=`
+	Le,
+
+	/// `>`
+	Gt,
+
+	/// `>=`
+	Ge,
+}
+
+impl CompareOp {
+	/// Applies the operator to the result of a comparison.
+	fn apply(self, ordering: std::cmp::Ordering) -> bool {
+		use std::cmp::Ordering::{Equal, Greater, Less};
+		match self {
+			Self::Eq => ordering == Equal,
+			Self::Ne => ordering != Equal,
+			Self::Lt => ordering == Less,
+			Self::Le => ordering != Greater,
+			Self::Gt => ordering == Greater,
+			Self::Ge => ordering != Less,
+		}
+	}
+}
+
+/// A literal value appearing on the right-hand side of a comparison.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Literal {
+	/// An integer literal.
+	Int(i64),
+
+	/// A string literal.
+	Str(String),
+}
+
+/// A lexical token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+	/// A field or keyword identifier.
+	Ident(String),
+
+	/// A string literal.
+	Str(String),
+
+	/// An integer literal.
+	Int(i64),
+
+	/// A comparison operator.
+	Op(CompareOp),
+
+	/// The `AND` keyword.
+	And,
+
+	/// The `OR` keyword.
+	Or,
+
+	/// The `NOT` keyword.
+	Not,
+
+	/// An opening parenthesis.
+	LParen,
+
+	/// A closing parenthesis.
+	RParen,
+}
+
+/// Scans an expression string into a list of tokens, each paired with the byte
+/// offset within `input` at which it begins.
+fn lex(input: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+	let bytes = input.as_bytes();
+	let mut tokens = Vec::new();
+	let mut i = 0;
+	while i < bytes.len() {
+		let c = bytes[i];
+		match c {
+			b' ' | b'\t' | b'\r' | b'\n' => i += 1,
+			b'(' => {
+				tokens.push((Token::LParen, i));
+				i += 1;
+			}
+			b')' => {
+				tokens.push((Token::RParen, i));
+				i += 1;
+			}
+			b'=' => {
+				tokens.push((Token::Op(CompareOp::Eq), i));
+				i += 1;
+			}
+			b'!' => {
+				if bytes.get(i + 1) == Some(&b'=') {
+					tokens.push((Token::Op(CompareOp::Ne), i));
+					i += 2;
+				} else {
+					return Err(FilterError {
+						offset: i,
+						message: "expected `=` after `!`".to_owned(),
+					});
+				}
+			}
+			b'<' => {
+				if bytes.get(i + 1) == Some(&b'=') {
+					tokens.push((Token::Op(CompareOp::Le), i));
+					i += 2;
+				} else {
+					tokens.push((Token::Op(CompareOp::Lt), i));
+					i += 1;
+				}
+			}
+			b'>' => {
+				if bytes.get(i + 1) == Some(&b'=') {
+					tokens.push((Token::Op(CompareOp::Ge), i));
+					i += 2;
+				} else {
+					tokens.push((Token::Op(CompareOp::Gt), i));
+					i += 1;
+				}
+			}
+			b'"' | b'\'' => {
+				let quote = c;
+				let start = i;
+				i += 1;
+				let mut value = String::new();
+				loop {
+					match bytes.get(i) {
+						None => {
+							return Err(FilterError {
+								offset: start,
+								message: "unterminated string literal".to_owned(),
+							});
+						}
+						Some(&b) if b == quote => {
+							i += 1;
+							break;
+						}
+						Some(&b) => {
+							value.push(char::from(b));
+							i += 1;
+						}
+					}
+				}
+				tokens.push((Token::Str(value), start));
+			}
+			b'0'..=b'9' | b'-' => {
+				let start = i;
+				i += 1;
+				while i < bytes.len() && bytes[i].is_ascii_digit() {
+					i += 1;
+				}
+				let text = &input[start..i];
+				let value = text.parse().map_err(|_| FilterError {
+					offset: start,
+					message: format!("invalid integer literal `{}`", text),
+				})?;
+				tokens.push((Token::Int(value), start));
+			}
+			_ if c.is_ascii_alphabetic() || c == b'_' => {
+				let start = i;
+				i += 1;
+				while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+					i += 1;
+				}
+				let word = &input[start..i];
+				tokens.push((
+					match word.to_ascii_uppercase().as_str() {
+						"AND" => Token::And,
+						"OR" => Token::Or,
+						"NOT" => Token::Not,
+						_ => Token::Ident(word.to_owned()),
+					},
+					start,
+				));
+			}
+			_ => {
+				return Err(FilterError {
+					offset: i,
+					message: format!("unexpected character `{}`", char::from(c)),
+				});
+			}
+		}
+	}
+	Ok(tokens)
+}
+
+/// A node in a parsed filter expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr {
+	/// A disjunction of two subexpressions.
+	Or(Box<Expr>, Box<Expr>),
+
+	/// A conjunction of two subexpressions.
+	And(Box<Expr>, Box<Expr>),
+
+	/// A negation of a subexpression.
+	Not(Box<Expr>),
+
+	/// A comparison of a field against a literal value.
+	Compare {
+		/// The name of the sample field being compared.
+		field: String,
+
+		/// The comparison operator.
+		op: CompareOp,
+
+		/// The literal value to compare against.
+		value: Literal,
+	},
+}
+
+/// A recursive-descent parser over a token list.
+struct Parser {
+	/// The tokens being consumed, each with its source byte offset.
+	tokens: Vec<(Token, usize)>,
+
+	/// The byte offset just past the end of the expression, reported when an
+	/// error occurs after the final token has been consumed.
+	end: usize,
+
+	/// The index of the next unconsumed token.
+	pos: usize,
+}
+
+impl Parser {
+	/// Returns the next token without consuming it.
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.pos).map(|(tok, _)| tok)
+	}
+
+	/// Consumes and returns the next token.
+	fn next(&mut self) -> Option<Token> {
+		let tok = self.tokens.get(self.pos).map(|(tok, _)| tok.clone());
+		if tok.is_some() {
+			self.pos += 1;
+		}
+		tok
+	}
+
+	/// Parses a full expression (lowest precedence: `OR`).
+	fn parse_or(&mut self) -> Result<Expr, FilterError> {
+		let mut lhs = self.parse_and()?;
+		while matches!(self.peek(), Some(Token::Or)) {
+			self.next();
+			let rhs = self.parse_and()?;
+			lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+		}
+		Ok(lhs)
+	}
+
+	/// Parses an `AND` expression.
+	fn parse_and(&mut self) -> Result<Expr, FilterError> {
+		let mut lhs = self.parse_not()?;
+		while matches!(self.peek(), Some(Token::And)) {
+			self.next();
+			let rhs = self.parse_not()?;
+			lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+		}
+		Ok(lhs)
+	}
+
+	/// Parses a `NOT` expression.
+	fn parse_not(&mut self) -> Result<Expr, FilterError> {
+		if matches!(self.peek(), Some(Token::Not)) {
+			self.next();
+			Ok(Expr::Not(Box::new(self.parse_not()?)))
+		} else {
+			self.parse_primary()
+		}
+	}
+
+	/// Parses a parenthesized group or a bare comparison.
+	fn parse_primary(&mut self) -> Result<Expr, FilterError> {
+		match self.next() {
+			Some(Token::LParen) => {
+				let inner = self.parse_or()?;
+				if matches!(self.peek(), Some(Token::RParen)) {
+					self.next();
+					Ok(inner)
+				} else {
+					Err(self.error("expected `)`"))
+				}
+			}
+			Some(Token::Ident(field)) => {
+				let op = match self.peek() {
+					Some(Token::Op(op)) => *op,
+					_ => return Err(self.error("expected comparison operator")),
+				};
+				self.next();
+				let value = match self.peek() {
+					Some(Token::Int(n)) => Literal::Int(*n),
+					Some(Token::Str(s)) => Literal::Str(s.clone()),
+					Some(Token::Ident(s)) => Literal::Str(s.clone()),
+					_ => return Err(self.error("expected a literal value")),
+				};
+				self.next();
+				Ok(Expr::Compare { field, op, value })
+			}
+			Some(_) => {
+				// Step back so the error points at the token just consumed.
+				self.pos -= 1;
+				Err(self.error("expected a field name or `(`"))
+			}
+			None => Err(self.error("expected a field name or `(`")),
+		}
+	}
+
+	/// Constructs a parse error pointing at the byte offset of the token that
+	/// stopped the parse, or the end of the expression if none remain.
+	fn error(&self, message: &str) -> FilterError {
+		let offset = self
+			.tokens
+			.get(self.pos)
+			.map_or(self.end, |(_, offset)| *offset);
+		FilterError {
+			offset,
+			message: message.to_owned(),
+		}
+	}
+}
+
+impl Expr {
+	/// Parses a filter expression from its textual form.
+	pub fn parse(input: &str) -> Result<Self, FilterError> {
+		let tokens = lex(input)?;
+		let mut parser = Parser {
+			tokens,
+			end: input.len(),
+			pos: 0,
+		};
+		let expr = parser.parse_or()?;
+		if parser.pos == parser.tokens.len() {
+			Ok(expr)
+		} else {
+			Err(parser.error("unexpected trailing tokens"))
+		}
+	}
+
+	/// Evaluates the expression against a single sample.
+	pub fn matches(&self, sample: &Sample) -> bool {
+		match self {
+			Self::Or(l, r) => l.matches(sample) || r.matches(sample),
+			Self::And(l, r) => l.matches(sample) && r.matches(sample),
+			Self::Not(e) => !e.matches(sample),
+			Self::Compare { field, op, value } => compare(sample, field, *op, value),
+		}
+	}
+}
+
+/// Evaluates a single comparison against a sample.
+///
+/// A field that does not apply to the sample's mod type makes the comparison
+/// evaluate to `false` rather than erroring.
+fn compare(sample: &Sample, field: &str, op: CompareOp, value: &Literal) -> bool {
+	match field {
+		"dimension" => compare_int(i64::from(sample.dimension), op, value),
+		"x" => compare_int(i64::from(sample.x), op, value),
+		"z" => compare_int(i64::from(sample.z), op, value),
+		"timestamp" => match &sample.data {
+			SampleData::Immersive(data) => {
+				// u64 timestamps may exceed i64; saturate for comparison purposes.
+				compare_int(i64::try_from(data.timestamp).unwrap_or(i64::MAX), op, value)
+			}
+			_ => false,
+		},
+		"mod" => {
+			let name = match &sample.data {
+				SampleData::Immersive(_) => "immersive",
+				SampleData::TerraFirmaCraft(_) => "tfc",
+				SampleData::Geolosys(_) => "geolosys",
+			};
+			compare_str(name, op, value)
+		}
+		"mineral" => match &sample.data {
+			SampleData::Immersive(data) => compare_str(&data.mineral, op, value),
+			_ => false,
+		},
+		"liquid" => match &sample.data {
+			SampleData::Immersive(data) => compare_str(&data.liquid, op, value),
+			_ => false,
+		},
+		"ore" => match &sample.data {
+			SampleData::TerraFirmaCraft(ore) | SampleData::Geolosys(ore) => {
+				compare_str(ore, op, value)
+			}
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// Compares a numeric field value against a literal, yielding `false` on a
+/// non-numeric literal.
+fn compare_int(field: i64, op: CompareOp, value: &Literal) -> bool {
+	match value {
+		Literal::Int(n) => op.apply(field.cmp(n)),
+		Literal::Str(_) => false,
+	}
+}
+
+/// Compares a string field value against a literal, yielding `false` on a
+/// non-string literal.
+fn compare_str(field: &str, op: CompareOp, value: &Literal) -> bool {
+	match value {
+		Literal::Str(s) => op.apply(field.cmp(s.as_str())),
+		Literal::Int(_) => false,
+	}
+}
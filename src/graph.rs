@@ -0,0 +1,128 @@
+//! GraphViz DOT export of samples as a spatial/deposit graph.
+//!
+//! The `graph` subcommand groups samples into per-dimension `subgraph
+//! cluster_*` blocks and draws an edge between every pair of samples in the
+//! same dimension whose chunk coordinates lie within a configurable Chebyshev
+//! distance, so piping the output through `dot` gives a map-like picture of
+//! where minerals and liquids cluster across dimensions. A small DOT writer is
+//! used rather than pulling in a heavy dependency.
+
+use crate::Sample;
+use std::io::Write;
+
+/// The kind of GraphViz document to emit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+	/// A directed graph, written with `->` edges.
+	Digraph,
+
+	/// An undirected graph, written with `--` edges.
+	Graph,
+}
+
+impl Kind {
+	/// Returns the leading keyword introducing the document.
+	fn keyword(self) -> &'static str {
+		match self {
+			Self::Digraph => "digraph",
+			Self::Graph => "graph",
+		}
+	}
+
+	/// Returns the operator joining the two endpoints of an edge.
+	fn edge_op(self) -> &'static str {
+		match self {
+			Self::Digraph => "->",
+			Self::Graph => "--",
+		}
+	}
+}
+
+/// Quotes and escapes a string for use as a DOT node ID or label.
+fn quote(s: &str) -> String {
+	let mut ret = String::with_capacity(s.len() + 2);
+	ret.push('"');
+	for c in s.chars() {
+		if c == '"' || c == '\\' {
+			ret.push('\\');
+		}
+		ret.push(c);
+	}
+	ret.push('"');
+	ret
+}
+
+/// Returns the Chebyshev distance between two samples' chunk coordinates.
+fn chebyshev(a: &Sample, b: &Sample) -> i64 {
+	let dx = (i64::from(a.x) - i64::from(b.x)).abs();
+	let dz = (i64::from(a.z) - i64::from(b.z)).abs();
+	dx.max(dz)
+}
+
+/// Writes the samples as a GraphViz document in the requested style.
+///
+/// Samples are grouped by dimension into `subgraph cluster_*` blocks and edges
+/// are drawn between same-dimension samples within `radius` chunks of each
+/// other, measured as a Chebyshev distance.
+pub fn write<W: Write>(
+	w: &mut W,
+	samples: &[Sample],
+	kind: Kind,
+	radius: u32,
+) -> std::io::Result<()> {
+	writeln!(w, "{} samples {{", kind.keyword())?;
+
+	// Emit one cluster per dimension, in first-seen order, listing each
+	// sample's node with a coordinate-and-summary label.
+	let mut dimensions: Vec<i32> = Vec::new();
+	for sample in samples {
+		if !dimensions.contains(&sample.dimension) {
+			dimensions.push(sample.dimension);
+		}
+	}
+	for dimension in &dimensions {
+		writeln!(w, "\tsubgraph cluster_{} {{", dimension_id(*dimension))?;
+		writeln!(w, "\t\tlabel = {};", quote(&format!("Dimension {}", dimension)))?;
+		for (index, sample) in samples.iter().enumerate() {
+			if sample.dimension == *dimension {
+				let label = format!("X={}, Z={}\n{}", sample.x, sample.z, sample.data);
+				writeln!(w, "\t\t{} [label = {}];", node_id(index), quote(&label))?;
+			}
+		}
+		writeln!(w, "\t}}")?;
+	}
+
+	// Draw an edge for each close pair within a dimension.
+	let radius = i64::from(radius);
+	for (i, a) in samples.iter().enumerate() {
+		for (j, b) in samples.iter().enumerate().skip(i + 1) {
+			if a.dimension == b.dimension && chebyshev(a, b) <= radius {
+				writeln!(
+					w,
+					"\t{} {} {};",
+					node_id(i),
+					kind.edge_op(),
+					node_id(j)
+				)?;
+			}
+		}
+	}
+
+	writeln!(w, "}}")?;
+	Ok(())
+}
+
+/// Returns the node ID for the sample at a given index.
+fn node_id(index: usize) -> String {
+	format!("s{}", index)
+}
+
+/// Returns a cluster name suffix for a dimension, keeping it a valid DOT ID by
+/// replacing the sign of a negative dimension with an `n` prefix.
+fn dimension_id(dimension: i32) -> String {
+	if dimension < 0 {
+		format!("n{}", dimension.unsigned_abs())
+	} else {
+		dimension.to_string()
+	}
+}
@@ -0,0 +1,554 @@
+//! Text interchange formats for round-tripping sample files.
+//!
+//! Both the JSON and CSV forms carry the full tagged [`SampleData`] so that an
+//! `export` followed by an `import` reproduces the original `Vec<Sample>`
+//! exactly, and both refuse to emit data that would overflow the `u16`
+//! string-length or `u32` count limits baked into the binary writer.
+
+use crate::{ImmersiveSampleData, Sample, SampleData};
+use std::io::{Read, Write};
+
+/// The selectable interchange format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+	/// A JSON array of tagged sample objects.
+	Json,
+
+	/// A CSV table with a fixed header.
+	Csv,
+}
+
+impl std::str::FromStr for Format {
+	type Err = std::io::Error;
+
+	fn from_str(s: &str) -> std::io::Result<Self> {
+		match s {
+			"json" => Ok(Self::Json),
+			"csv" => Ok(Self::Csv),
+			_ => Err(invalid(format!("unknown format `{}`", s))),
+		}
+	}
+}
+
+/// Constructs an `InvalidData` error, matching the binary reader's discipline.
+fn invalid(message: impl Into<String>) -> std::io::Error {
+	std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+/// Checks that the sample list can be written back to the binary format
+/// without overflowing its `u16` string-length or `u32` count limits.
+fn check_limits(samples: &[Sample]) -> std::io::Result<()> {
+	if u32::try_from(samples.len()).is_err() {
+		return Err(invalid("too many samples for the binary format"));
+	}
+	for (index, sample) in samples.iter().enumerate() {
+		let strings: &[&str] = match &sample.data {
+			SampleData::Immersive(data) => &[&data.mineral, &data.liquid],
+			SampleData::TerraFirmaCraft(ore) | SampleData::Geolosys(ore) => &[ore.as_str()],
+		};
+		for s in strings {
+			if u16::try_from(s.len()).is_err() {
+				return Err(invalid(format!(
+					"sample {}: string is too long for the binary format",
+					index
+				)));
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Writes the samples to a writer in the requested format.
+pub fn export<W: Write>(w: &mut W, samples: &[Sample], format: Format) -> std::io::Result<()> {
+	check_limits(samples)?;
+	match format {
+		Format::Json => export_json(w, samples),
+		Format::Csv => export_csv(w, samples),
+	}
+}
+
+/// Reads samples from a reader in the requested format.
+pub fn import<R: Read>(r: &mut R, format: Format) -> std::io::Result<Vec<Sample>> {
+	let mut text = String::new();
+	r.read_to_string(&mut text)?;
+	let samples = match format {
+		Format::Json => import_json(&text)?,
+		Format::Csv => import_csv(&text)?,
+	};
+	check_limits(&samples)?;
+	Ok(samples)
+}
+
+// --- JSON ------------------------------------------------------------------
+
+/// Appends a JSON-escaped string, including the surrounding quotes.
+fn push_json_string(out: &mut String, s: &str) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+/// Serializes the samples as a JSON array of tagged objects.
+fn export_json<W: Write>(w: &mut W, samples: &[Sample]) -> std::io::Result<()> {
+	let mut out = String::new();
+	if samples.is_empty() {
+		out.push_str("[]");
+	} else {
+		out.push_str("[\n");
+		for (index, sample) in samples.iter().enumerate() {
+			out.push_str("  {");
+			out.push_str("\"mod\": ");
+			let kind = match &sample.data {
+				SampleData::Immersive(_) => "immersive",
+				SampleData::TerraFirmaCraft(_) => "tfc",
+				SampleData::Geolosys(_) => "geolosys",
+			};
+			push_json_string(&mut out, kind);
+			out.push_str(&format!(
+				", \"dimension\": {}, \"x\": {}, \"z\": {}",
+				sample.dimension, sample.x, sample.z
+			));
+			match &sample.data {
+				SampleData::Immersive(data) => {
+					out.push_str(", \"mineral\": ");
+					push_json_string(&mut out, &data.mineral);
+					out.push_str(", \"liquid\": ");
+					push_json_string(&mut out, &data.liquid);
+					out.push_str(&format!(", \"timestamp\": {}", data.timestamp));
+				}
+				SampleData::TerraFirmaCraft(ore) | SampleData::Geolosys(ore) => {
+					out.push_str(", \"ore\": ");
+					push_json_string(&mut out, ore);
+				}
+			}
+			out.push('}');
+			if index + 1 < samples.len() {
+				out.push(',');
+			}
+			out.push('\n');
+		}
+		out.push(']');
+	}
+	out.push('\n');
+	w.write_all(out.as_bytes())
+}
+
+/// A minimal JSON value, covering only the subset this format emits.
+enum Json {
+	/// A string.
+	Str(String),
+
+	/// A number, kept as its raw text so the field decides the numeric type.
+	Num(String),
+
+	/// An array.
+	Arr(Vec<Json>),
+
+	/// An object, preserving insertion order.
+	Obj(Vec<(String, Json)>),
+}
+
+/// A hand-rolled recursive-descent parser for the JSON subset.
+struct JsonParser<'a> {
+	/// The input bytes.
+	bytes: &'a [u8],
+
+	/// The index of the next unconsumed byte.
+	pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+	/// Skips insignificant whitespace.
+	fn skip_ws(&mut self) {
+		while let Some(&c) = self.bytes.get(self.pos) {
+			if c == b' ' || c == b'\t' || c == b'\r' || c == b'\n' {
+				self.pos += 1;
+			} else {
+				break;
+			}
+		}
+	}
+
+	/// Consumes an expected byte or fails.
+	fn expect(&mut self, byte: u8) -> std::io::Result<()> {
+		self.skip_ws();
+		if self.bytes.get(self.pos) == Some(&byte) {
+			self.pos += 1;
+			Ok(())
+		} else {
+			Err(invalid(format!(
+				"expected `{}` at offset {}",
+				char::from(byte),
+				self.pos
+			)))
+		}
+	}
+
+	/// Parses a single value.
+	fn value(&mut self) -> std::io::Result<Json> {
+		self.skip_ws();
+		match self.bytes.get(self.pos) {
+			Some(b'"') => Ok(Json::Str(self.string()?)),
+			Some(b'[') => self.array(),
+			Some(b'{') => self.object(),
+			Some(&c) if c == b'-' || c.is_ascii_digit() => self.number(),
+			_ => Err(invalid(format!("unexpected JSON token at offset {}", self.pos))),
+		}
+	}
+
+	/// Parses a string literal.
+	fn string(&mut self) -> std::io::Result<String> {
+		self.expect(b'"')?;
+		let mut out = String::new();
+		loop {
+			match self.bytes.get(self.pos) {
+				None => return Err(invalid("unterminated JSON string")),
+				Some(b'"') => {
+					self.pos += 1;
+					return Ok(out);
+				}
+				Some(b'\\') => {
+					self.pos += 1;
+					match self.bytes.get(self.pos) {
+						Some(b'"') => out.push('"'),
+						Some(b'\\') => out.push('\\'),
+						Some(b'/') => out.push('/'),
+						Some(b'n') => out.push('\n'),
+						Some(b'r') => out.push('\r'),
+						Some(b't') => out.push('\t'),
+						Some(b'u') => {
+							let hex = self
+								.bytes
+								.get(self.pos + 1..self.pos + 5)
+								.ok_or_else(|| invalid("truncated \\u escape"))?;
+							let code = u32::from_str_radix(
+								std::str::from_utf8(hex)
+									.map_err(|_| invalid("invalid \\u escape"))?,
+								16,
+							)
+							.map_err(|_| invalid("invalid \\u escape"))?;
+							out.push(char::from_u32(code).ok_or_else(|| invalid("invalid \\u escape"))?);
+							self.pos += 4;
+						}
+						_ => return Err(invalid("invalid JSON escape")),
+					}
+					self.pos += 1;
+				}
+				Some(_) => {
+					// Consume one UTF-8 character.
+					let rest = std::str::from_utf8(&self.bytes[self.pos..])
+						.map_err(|_| invalid("invalid UTF-8 in JSON string"))?;
+					let c = rest.chars().next().unwrap();
+					out.push(c);
+					self.pos += c.len_utf8();
+				}
+			}
+		}
+	}
+
+	/// Parses a numeric literal, retaining its text.
+	fn number(&mut self) -> std::io::Result<Json> {
+		let start = self.pos;
+		if self.bytes.get(self.pos) == Some(&b'-') {
+			self.pos += 1;
+		}
+		while matches!(self.bytes.get(self.pos), Some(c) if c.is_ascii_digit()) {
+			self.pos += 1;
+		}
+		if self.pos == start {
+			return Err(invalid(format!("expected a number at offset {}", start)));
+		}
+		Ok(Json::Num(
+			std::str::from_utf8(&self.bytes[start..self.pos]).unwrap().to_owned(),
+		))
+	}
+
+	/// Parses an array.
+	fn array(&mut self) -> std::io::Result<Json> {
+		self.expect(b'[')?;
+		let mut items = Vec::new();
+		self.skip_ws();
+		if self.bytes.get(self.pos) == Some(&b']') {
+			self.pos += 1;
+			return Ok(Json::Arr(items));
+		}
+		loop {
+			items.push(self.value()?);
+			self.skip_ws();
+			match self.bytes.get(self.pos) {
+				Some(b',') => self.pos += 1,
+				Some(b']') => {
+					self.pos += 1;
+					return Ok(Json::Arr(items));
+				}
+				_ => return Err(invalid(format!("expected `,` or `]` at offset {}", self.pos))),
+			}
+		}
+	}
+
+	/// Parses an object.
+	fn object(&mut self) -> std::io::Result<Json> {
+		self.expect(b'{')?;
+		let mut fields = Vec::new();
+		self.skip_ws();
+		if self.bytes.get(self.pos) == Some(&b'}') {
+			self.pos += 1;
+			return Ok(Json::Obj(fields));
+		}
+		loop {
+			self.skip_ws();
+			let key = self.string()?;
+			self.expect(b':')?;
+			let value = self.value()?;
+			fields.push((key, value));
+			self.skip_ws();
+			match self.bytes.get(self.pos) {
+				Some(b',') => self.pos += 1,
+				Some(b'}') => {
+					self.pos += 1;
+					return Ok(Json::Obj(fields));
+				}
+				_ => return Err(invalid(format!("expected `,` or `}}` at offset {}", self.pos))),
+			}
+		}
+	}
+}
+
+impl Json {
+	/// Returns a field of an object, or an error if absent.
+	fn field<'a>(fields: &'a [(String, Self)], name: &str) -> std::io::Result<&'a Self> {
+		fields
+			.iter()
+			.find(|(k, _)| k == name)
+			.map(|(_, v)| v)
+			.ok_or_else(|| invalid(format!("missing `{}` field", name)))
+	}
+
+	/// Interprets the value as a string.
+	fn as_str(&self) -> std::io::Result<&str> {
+		match self {
+			Self::Str(s) => Ok(s),
+			_ => Err(invalid("expected a string value")),
+		}
+	}
+
+	/// Parses the value as a number of the requested type.
+	fn as_num<T: std::str::FromStr>(&self, field: &str) -> std::io::Result<T> {
+		match self {
+			Self::Num(s) => s
+				.parse()
+				.map_err(|_| invalid(format!("`{}` is out of range", field))),
+			_ => Err(invalid(format!("`{}` must be a number", field))),
+		}
+	}
+}
+
+/// Deserializes samples from the JSON array form.
+fn import_json(text: &str) -> std::io::Result<Vec<Sample>> {
+	let mut parser = JsonParser {
+		bytes: text.as_bytes(),
+		pos: 0,
+	};
+	let value = parser.value()?;
+	parser.skip_ws();
+	if parser.pos != parser.bytes.len() {
+		return Err(invalid(format!("trailing data at offset {}", parser.pos)));
+	}
+	let array = match value {
+		Json::Arr(items) => items,
+		_ => return Err(invalid("expected a JSON array of samples")),
+	};
+	let mut samples = Vec::with_capacity(array.len());
+	for item in array {
+		let fields = match item {
+			Json::Obj(fields) => fields,
+			_ => return Err(invalid("expected a JSON object for each sample")),
+		};
+		let dimension = Json::field(&fields, "dimension")?.as_num("dimension")?;
+		let x = Json::field(&fields, "x")?.as_num("x")?;
+		let z = Json::field(&fields, "z")?.as_num("z")?;
+		let data = match Json::field(&fields, "mod")?.as_str()? {
+			"immersive" => SampleData::Immersive(ImmersiveSampleData {
+				mineral: Json::field(&fields, "mineral")?.as_str()?.to_owned(),
+				liquid: Json::field(&fields, "liquid")?.as_str()?.to_owned(),
+				timestamp: Json::field(&fields, "timestamp")?.as_num("timestamp")?,
+			}),
+			"tfc" => SampleData::TerraFirmaCraft(Json::field(&fields, "ore")?.as_str()?.to_owned()),
+			"geolosys" => SampleData::Geolosys(Json::field(&fields, "ore")?.as_str()?.to_owned()),
+			other => return Err(invalid(format!("unknown mod `{}`", other))),
+		};
+		samples.push(Sample {
+			dimension,
+			x,
+			z,
+			data,
+		});
+	}
+	Ok(samples)
+}
+
+// --- CSV -------------------------------------------------------------------
+
+/// The fixed CSV header.
+const CSV_HEADER: &str = "mod,dimension,x,z,mineral,liquid,timestamp,ore";
+
+/// Appends one CSV field, quoting it if required.
+fn push_csv_field(out: &mut String, field: &str) {
+	if field.contains([',', '"', '\n', '\r']) {
+		out.push('"');
+		for c in field.chars() {
+			if c == '"' {
+				out.push('"');
+			}
+			out.push(c);
+		}
+		out.push('"');
+	} else {
+		out.push_str(field);
+	}
+}
+
+/// Serializes the samples as CSV with the fixed header.
+fn export_csv<W: Write>(w: &mut W, samples: &[Sample]) -> std::io::Result<()> {
+	let mut out = String::from(CSV_HEADER);
+	out.push('\n');
+	for sample in samples {
+		let (kind, mineral, liquid, timestamp, ore) = match &sample.data {
+			SampleData::Immersive(data) => (
+				"immersive",
+				data.mineral.clone(),
+				data.liquid.clone(),
+				data.timestamp.to_string(),
+				String::new(),
+			),
+			SampleData::TerraFirmaCraft(ore) => {
+				("tfc", String::new(), String::new(), String::new(), ore.clone())
+			}
+			SampleData::Geolosys(ore) => {
+				("geolosys", String::new(), String::new(), String::new(), ore.clone())
+			}
+		};
+		let fields = [
+			kind.to_owned(),
+			sample.dimension.to_string(),
+			sample.x.to_string(),
+			sample.z.to_string(),
+			mineral,
+			liquid,
+			timestamp,
+			ore,
+		];
+		for (index, field) in fields.iter().enumerate() {
+			if index != 0 {
+				out.push(',');
+			}
+			push_csv_field(&mut out, field);
+		}
+		out.push('\n');
+	}
+	w.write_all(out.as_bytes())
+}
+
+/// Splits the full CSV text into records, honouring quoted fields that may
+/// themselves contain commas or newlines.
+fn parse_csv_records(text: &str) -> std::io::Result<Vec<Vec<String>>> {
+	let mut records = Vec::new();
+	let mut fields = Vec::new();
+	let mut field = String::new();
+	let mut in_quotes = false;
+	let mut chars = text.chars().peekable();
+	while let Some(c) = chars.next() {
+		if in_quotes {
+			match c {
+				'"' => {
+					if chars.peek() == Some(&'"') {
+						chars.next();
+						field.push('"');
+					} else {
+						in_quotes = false;
+					}
+				}
+				_ => field.push(c),
+			}
+		} else {
+			match c {
+				'"' => in_quotes = true,
+				',' => fields.push(std::mem::take(&mut field)),
+				// Swallow carriage returns so `\r\n` line endings leave no trace.
+				'\r' => {}
+				'\n' => {
+					fields.push(std::mem::take(&mut field));
+					records.push(std::mem::take(&mut fields));
+				}
+				_ => field.push(c),
+			}
+		}
+	}
+	if in_quotes {
+		return Err(invalid("unterminated quoted CSV field"));
+	}
+	// Flush a final record not terminated by a newline.
+	if !field.is_empty() || !fields.is_empty() {
+		fields.push(field);
+		records.push(fields);
+	}
+	Ok(records)
+}
+
+/// Deserializes samples from the CSV form.
+fn import_csv(text: &str) -> std::io::Result<Vec<Sample>> {
+	let mut records = parse_csv_records(text)?.into_iter();
+	match records.next() {
+		Some(header) if header.iter().map(String::as_str).eq(CSV_HEADER.split(',')) => {}
+		_ => return Err(invalid("missing or unexpected CSV header")),
+	}
+	let mut samples = Vec::new();
+	for record in records {
+		// Skip blank lines, which parse as a single empty field.
+		if record.len() == 1 && record[0].is_empty() {
+			continue;
+		}
+		if record.len() != 8 {
+			return Err(invalid(format!(
+				"expected 8 CSV columns, found {}",
+				record.len()
+			)));
+		}
+		let dimension = parse_csv_num(&record[1], "dimension")?;
+		let x = parse_csv_num(&record[2], "x")?;
+		let z = parse_csv_num(&record[3], "z")?;
+		let data = match record[0].as_str() {
+			"immersive" => SampleData::Immersive(ImmersiveSampleData {
+				mineral: record[4].clone(),
+				liquid: record[5].clone(),
+				timestamp: parse_csv_num(&record[6], "timestamp")?,
+			}),
+			"tfc" => SampleData::TerraFirmaCraft(record[7].clone()),
+			"geolosys" => SampleData::Geolosys(record[7].clone()),
+			other => return Err(invalid(format!("unknown mod `{}`", other))),
+		};
+		samples.push(Sample {
+			dimension,
+			x,
+			z,
+			data,
+		});
+	}
+	Ok(samples)
+}
+
+/// Parses a numeric CSV cell, reporting the column name on failure.
+fn parse_csv_num<T: std::str::FromStr>(cell: &str, field: &str) -> std::io::Result<T> {
+	cell.parse()
+		.map_err(|_| invalid(format!("invalid `{}` value `{}`", field, cell)))
+}
@@ -23,12 +23,49 @@
 	clippy::pedantic,
 )]
 
+mod error;
+mod filter;
+mod graph;
+mod interchange;
+mod script;
+
 use clap::{App, AppSettings, Arg, ArgMatches};
+use error::{Field, TrackerError};
 use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
+/// A reader that tracks how many bytes have been consumed, so field failures
+/// can be annotated with their byte offset within the file.
+struct CountingReader<R> {
+	/// The underlying reader.
+	inner: R,
+
+	/// The number of bytes consumed so far.
+	count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+	/// Wraps a reader.
+	fn new(inner: R) -> Self {
+		Self { inner, count: 0 }
+	}
+
+	/// Returns the current byte offset within the file.
+	fn position(&self) -> u64 {
+		self.count
+	}
+}
+
+impl<R: Read> Read for CountingReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		let n = self.inner.read(buf)?;
+		self.count += n as u64;
+		Ok(n)
+	}
+}
+
 /// Reads a two-byte length-prefixed string from a file.
 fn read_string<R: Read>(r: &mut R) -> std::io::Result<String> {
 	let mut len = [0_u8; 2];
@@ -65,11 +102,20 @@ struct ImmersiveSampleData {
 
 impl ImmersiveSampleData {
 	/// Reads the Immersive-specific portion of a sample from a file.
-	pub fn read_from<R: Read>(r: &mut R) -> std::io::Result<Self> {
-		let mineral = read_string(r)?;
-		let liquid = read_string(r)?;
+	pub fn read_from<R: Read>(
+		r: &mut CountingReader<R>,
+		sample: usize,
+	) -> Result<Self, TrackerError> {
+		let offset = r.position();
+		let mineral = read_string(r)
+			.map_err(|e| TrackerError::read(Some(sample), Field::Mineral, offset, e))?;
+		let offset = r.position();
+		let liquid = read_string(r)
+			.map_err(|e| TrackerError::read(Some(sample), Field::Liquid, offset, e))?;
+		let offset = r.position();
 		let mut timestamp = [0_u8; 8];
-		r.read_exact(&mut timestamp)?;
+		r.read_exact(&mut timestamp)
+			.map_err(|e| TrackerError::read(Some(sample), Field::Timestamp, offset, e))?;
 		let timestamp = u64::from_be_bytes(timestamp);
 		Ok(Self {
 			mineral,
@@ -137,23 +183,44 @@ struct Sample {
 
 impl Sample {
 	/// Reads a sample from a file.
-	pub fn read_from<R: Read>(r: &mut R) -> std::io::Result<Self> {
+	pub fn read_from<R: Read>(
+		r: &mut CountingReader<R>,
+		sample: usize,
+	) -> Result<Self, TrackerError> {
+		let offset = r.position();
 		let mut source_mod = [0_u8; 4];
-		r.read_exact(&mut source_mod)?;
+		r.read_exact(&mut source_mod)
+			.map_err(|e| TrackerError::read(Some(sample), Field::SourceMod, offset, e))?;
 		let source_mod = u32::from_be_bytes(source_mod);
 		if source_mod == 0 || source_mod == 1 || source_mod == 2 {
+			let offset = r.position();
 			let mut dimension = [0_u8; 4];
-			r.read_exact(&mut dimension)?;
+			r.read_exact(&mut dimension)
+				.map_err(|e| TrackerError::read(Some(sample), Field::Dimension, offset, e))?;
 			let dimension = i32::from_be_bytes(dimension);
+			let offset = r.position();
 			let mut buf4 = [0_u8; 4];
-			r.read_exact(&mut buf4)?;
+			r.read_exact(&mut buf4)
+				.map_err(|e| TrackerError::read(Some(sample), Field::X, offset, e))?;
 			let x = i32::from_be_bytes(buf4);
-			r.read_exact(&mut buf4)?;
+			let offset = r.position();
+			r.read_exact(&mut buf4)
+				.map_err(|e| TrackerError::read(Some(sample), Field::Z, offset, e))?;
 			let z = i32::from_be_bytes(buf4);
 			let data = match source_mod {
-				0 => SampleData::Immersive(ImmersiveSampleData::read_from(r)?),
-				1 => SampleData::TerraFirmaCraft(read_string(r)?),
-				2 => SampleData::Geolosys(read_string(r)?),
+				0 => SampleData::Immersive(ImmersiveSampleData::read_from(r, sample)?),
+				1 => {
+					let offset = r.position();
+					SampleData::TerraFirmaCraft(read_string(r).map_err(|e| {
+						TrackerError::read(Some(sample), Field::Ore, offset, e)
+					})?)
+				}
+				2 => {
+					let offset = r.position();
+					SampleData::Geolosys(read_string(r).map_err(|e| {
+						TrackerError::read(Some(sample), Field::Ore, offset, e)
+					})?)
+				}
 				_ => unreachable!(),
 			};
 			Ok(Self {
@@ -163,24 +230,33 @@ impl Sample {
 				data,
 			})
 		} else {
-			Err(std::io::Error::new(
-				std::io::ErrorKind::InvalidData,
-				"invalid sample type",
+			Err(TrackerError::read(
+				Some(sample),
+				Field::SourceMod,
+				offset,
+				std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid sample type"),
 			))
 		}
 	}
 
 	/// Reads a counted list of samples from a file.
-	pub fn read_list_from<R: Read>(r: &mut R) -> std::io::Result<Vec<Self>> {
+	pub fn read_list_from<R: Read>(r: &mut CountingReader<R>) -> Result<Vec<Self>, TrackerError> {
+		let offset = r.position();
 		let mut count = [0_u8; 4];
-		r.read_exact(&mut count)?;
+		r.read_exact(&mut count)
+			.map_err(|e| TrackerError::read(None, Field::Count, offset, e))?;
 		let count = u32::from_be_bytes(count);
-		let count: usize = count
-			.try_into()
-			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+		let count: usize = count.try_into().map_err(|e| {
+			TrackerError::read(
+				None,
+				Field::Count,
+				offset,
+				std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+			)
+		})?;
 		let mut ret = Vec::with_capacity(count);
-		for _ in 0..count {
-			ret.push(Self::read_from(r)?);
+		for index in 0..count {
+			ret.push(Self::read_from(r, index)?);
 		}
 		Ok(ret)
 	}
@@ -231,8 +307,8 @@ impl Display for Sample {
 }
 
 /// Reads a sample file.
-fn read_file(file: impl AsRef<Path>) -> std::io::Result<Vec<Sample>> {
-	Sample::read_list_from(&mut BufReader::new(File::open(file)?))
+fn read_file(file: impl AsRef<Path>) -> Result<Vec<Sample>, TrackerError> {
+	Sample::read_list_from(&mut CountingReader::new(BufReader::new(File::open(file)?)))
 }
 
 /// Writes a sample file.
@@ -245,32 +321,29 @@ fn write_file(file: impl AsRef<Path>, samples: &[Sample]) -> std::io::Result<()>
 	Ok(())
 }
 
-/// Given a string, converts it to a desired type, and exits with a given message if it is not
-/// convertible.
-fn convert<T: std::str::FromStr>(s: impl AsRef<str>, message: &str) -> T {
-	if let Ok(n) = s.as_ref().parse() {
-		n
-	} else {
-		eprintln!("{}", message);
-		std::process::exit(1);
-	}
+/// Given a string, converts it to a desired type, returning a context-carrying
+/// error with a given message if it is not convertible.
+fn convert<T: std::str::FromStr>(s: impl AsRef<str>, message: &str) -> Result<T, TrackerError> {
+	s.as_ref()
+		.parse()
+		.map_err(|_| TrackerError::Message(message.to_owned()))
 }
 
 /// Implements the `edit` command.
-fn do_edit(matches: &ArgMatches<'_>, file: impl AsRef<Path>) -> std::io::Result<()> {
+fn do_edit(matches: &ArgMatches<'_>, file: impl AsRef<Path>) -> Result<(), TrackerError> {
 	let mut samples = read_file(&file)?;
 	let dimension: i32 = convert(
 		matches.value_of("dimension").unwrap(),
 		"Dimension ID must be an integer",
-	);
+	)?;
 	let x: i32 = convert(
 		matches.value_of("x").unwrap(),
 		"X coordinate must be an integer",
-	);
+	)?;
 	let z: i32 = convert(
 		matches.value_of("z").unwrap(),
 		"Z coordinate must be an integer",
-	);
+	)?;
 	let mineral = matches.value_of("mineral");
 	let liquid = matches.value_of("liquid");
 	let ore = matches.value_of("ore");
@@ -299,23 +372,21 @@ fn do_edit(matches: &ArgMatches<'_>, file: impl AsRef<Path>) -> std::io::Result<
 	}
 	if found {
 		write_file(file, &samples)?;
+		Ok(())
 	} else if ore.is_some() {
-		eprintln!(
+		Err(TrackerError::Message(format!(
 			"No TFC or Geolosys sample found in dimension {} at X={}, Z={}",
 			dimension, x, z
-		);
-		std::process::exit(1);
+		)))
 	} else {
-		eprintln!(
+		Err(TrackerError::Message(format!(
 			"No Immersive sample found in dimension {} at X={}, Z={}",
 			dimension, x, z
-		);
-		std::process::exit(1);
+		)))
 	}
-	Ok(())
 }
 
-fn main() -> std::io::Result<()> {
+fn main() -> Result<(), TrackerError> {
 	let matches = App::new("barotool")
 		.author(clap::crate_authors!())
 		.about("Manipulates Minecraft Mineral Tracker data files.")
@@ -374,16 +445,114 @@ fn main() -> std::io::Result<()> {
 						.required_unless_one(&["mineral", "liquid"]),
 				),
 		)
-		.subcommand(App::new("list").about("Lists the samples in a file."))
+		.subcommand(
+			App::new("list").about("Lists the samples in a file.").arg(
+				Arg::with_name("filter")
+					.long("filter")
+					.short("f")
+					.help("A boolean query restricting which samples are listed")
+					.takes_value(true),
+			),
+		)
+		.subcommand(
+			App::new("export")
+				.about("Exports a file to a JSON or CSV interchange format on stdout.")
+				.arg(
+					Arg::with_name("format")
+						.long("format")
+						.help("The interchange format to write")
+						.takes_value(true)
+						.possible_values(&["json", "csv"])
+						.default_value("json"),
+				),
+		)
+		.subcommand(
+			App::new("import")
+				.about("Imports a JSON or CSV interchange format from stdin into a file.")
+				.arg(
+					Arg::with_name("format")
+						.long("format")
+						.help("The interchange format to read")
+						.takes_value(true)
+						.possible_values(&["json", "csv"])
+						.default_value("json"),
+				),
+		)
+		.subcommand(
+			App::new("apply")
+				.about("Applies a batch edit script to a file in a single pass.")
+				.arg(
+					Arg::with_name("script")
+						.help("The edit script to apply")
+						.required(true),
+				),
+		)
+		.subcommand(
+			App::new("graph")
+				.about("Emits a GraphViz DOT graph of the samples on stdout.")
+				.arg(
+					Arg::with_name("directed")
+						.long("directed")
+						.help("Emit a directed graph instead of an undirected one"),
+				)
+				.arg(
+					Arg::with_name("radius")
+						.long("radius")
+						.help("The Chebyshev chunk distance within which samples are linked")
+						.takes_value(true)
+						.default_value("4"),
+				),
+		)
 		.get_matches();
 	let file = matches.value_of_os("file").unwrap();
 	if let Some(matches) = matches.subcommand_matches("edit") {
 		do_edit(matches, file)
-	} else if matches.subcommand_matches("list").is_some() {
+	} else if let Some(matches) = matches.subcommand_matches("list") {
+		let expr = matches
+			.value_of("filter")
+			.map(|s| {
+				filter::Expr::parse(s)
+					.map_err(|e| TrackerError::Message(format!("Invalid filter expression: {}", e)))
+			})
+			.transpose()?;
 		for sample in read_file(file)? {
-			println!("{}", sample);
+			if expr.as_ref().map_or(true, |e| e.matches(&sample)) {
+				println!("{}", sample);
+			}
 		}
 		Ok(())
+	} else if let Some(matches) = matches.subcommand_matches("export") {
+		let format = matches.value_of("format").unwrap().parse().unwrap();
+		let samples = read_file(file)?;
+		let stdout = std::io::stdout();
+		interchange::export(&mut stdout.lock(), &samples, format)?;
+		Ok(())
+	} else if let Some(matches) = matches.subcommand_matches("import") {
+		let format = matches.value_of("format").unwrap().parse().unwrap();
+		let stdin = std::io::stdin();
+		let samples = interchange::import(&mut stdin.lock(), format)?;
+		write_file(file, &samples)?;
+		Ok(())
+	} else if let Some(matches) = matches.subcommand_matches("apply") {
+		let ops = script::parse(matches.value_of_os("script").unwrap())?;
+		let mut samples = read_file(file)?;
+		script::apply(&mut samples, &ops)?;
+		write_file(file, &samples)?;
+		Ok(())
+	} else if let Some(matches) = matches.subcommand_matches("graph") {
+		let kind = if matches.is_present("directed") {
+			graph::Kind::Digraph
+		} else {
+			graph::Kind::Graph
+		};
+		let radius: u32 = convert(
+			matches.value_of("radius").unwrap(),
+			"Radius must be a non-negative integer",
+		)?;
+		let samples = read_file(file)?;
+		let stdout = std::io::stdout();
+		graph::write(&mut stdout.lock(), &samples, kind, radius)?;
+		Ok(())
 	} else {
 		panic!("no subcommand")
 	}
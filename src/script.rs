@@ -0,0 +1,274 @@
+//! Batch edit scripts for the `apply` subcommand.
+//!
+//! A script is parsed — resolving `include` directives depth-first with cycle
+//! detection — into an ordered list of [`Operation`]s before any data is
+//! touched, so a run either applies every directive or fails without writing.
+
+use crate::{Sample, SampleData, TrackerError};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The field a directive targets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EditField {
+	/// The Immersive mineral name.
+	Mineral,
+
+	/// The Immersive liquid name.
+	Liquid,
+
+	/// The TerraFirmaCraft or Geolosys ore name.
+	Ore,
+}
+
+impl EditField {
+	/// Parses a field name.
+	fn parse(name: &str) -> Option<Self> {
+		match name {
+			"mineral" => Some(Self::Mineral),
+			"liquid" => Some(Self::Liquid),
+			"ore" => Some(Self::Ore),
+			_ => None,
+		}
+	}
+
+	/// Returns the field's name as written in a script.
+	fn name(self) -> &'static str {
+		match self {
+			Self::Mineral => "mineral",
+			Self::Liquid => "liquid",
+			Self::Ore => "ore",
+		}
+	}
+}
+
+/// A single parsed edit operation.
+pub struct Operation {
+	/// The dimension ID of the sample to edit.
+	dimension: i32,
+
+	/// The chunk X coordinate of the sample to edit.
+	x: i32,
+
+	/// The chunk Z coordinate of the sample to edit.
+	z: i32,
+
+	/// The field to modify.
+	field: EditField,
+
+	/// The value to store, empty for an `unset`.
+	value: String,
+
+	/// Whether this directive is an `unset` (for reporting purposes).
+	unset: bool,
+}
+
+/// Splits the leading whitespace-separated token off a string.
+fn take_token(s: &str) -> (&str, &str) {
+	let s = s.trim_start();
+	match s.find(char::is_whitespace) {
+		Some(i) => (&s[..i], s[i..].trim_start()),
+		None => (s, ""),
+	}
+}
+
+/// Parses an integer directive operand, reporting its location on failure.
+fn parse_int(s: &str, path: &Path, line: usize, what: &str) -> Result<i32, TrackerError> {
+	s.parse().map_err(|_| {
+		TrackerError::Message(format!(
+			"{}:{}: {} must be an integer",
+			path.display(),
+			line,
+			what
+		))
+	})
+}
+
+/// Parses a script file into a flat, ordered list of operations.
+pub fn parse(path: impl AsRef<Path>) -> Result<Vec<Operation>, TrackerError> {
+	let mut ops = Vec::new();
+	let mut stack = HashSet::new();
+	parse_into(path.as_ref(), None, &mut ops, &mut stack)?;
+	Ok(ops)
+}
+
+/// Parses one script file, appending its operations and recursing into any
+/// `include` directives with cycle detection.
+///
+/// `from` names the script and line of the `include` directive that pulled in
+/// this file, if any, so a failure to open it carries the same
+/// `<file>:<line>:` context as every other directive error.
+fn parse_into(
+	path: &Path,
+	from: Option<(&Path, usize)>,
+	ops: &mut Vec<Operation>,
+	stack: &mut HashSet<PathBuf>,
+) -> Result<(), TrackerError> {
+	let context = |e: &std::io::Error| match from {
+		Some((script, line)) => TrackerError::Message(format!(
+			"{}:{}: cannot open include `{}`: {}",
+			script.display(),
+			line,
+			path.display(),
+			e
+		)),
+		None => TrackerError::Message(format!("cannot open script `{}`: {}", path.display(), e)),
+	};
+	let canonical = path.canonicalize().map_err(|e| context(&e))?;
+	if !stack.insert(canonical.clone()) {
+		return Err(TrackerError::Message(format!(
+			"include cycle detected at {}",
+			path.display()
+		)));
+	}
+	let text = std::fs::read_to_string(&canonical).map_err(|e| context(&e))?;
+	for (index, raw) in text.lines().enumerate() {
+		let line = index + 1;
+		let trimmed = raw.trim();
+		if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+			continue;
+		}
+		let (command, rest) = take_token(trimmed);
+		match command {
+			"include" => {
+				if rest.is_empty() {
+					return Err(TrackerError::Message(format!(
+						"{}:{}: include requires a path",
+						path.display(),
+						line
+					)));
+				}
+				let included = canonical
+					.parent()
+					.unwrap_or_else(|| Path::new("."))
+					.join(rest);
+				parse_into(&included, Some((path, line)), ops, stack)?;
+			}
+			"set" | "unset" => {
+				ops.push(parse_edit(command, rest, &canonical, line)?);
+			}
+			other => {
+				return Err(TrackerError::Message(format!(
+					"{}:{}: unknown directive `{}`",
+					path.display(),
+					line,
+					other
+				)));
+			}
+		}
+	}
+	stack.remove(&canonical);
+	Ok(())
+}
+
+/// Parses a `set` or `unset` directive.
+fn parse_edit(
+	command: &str,
+	rest: &str,
+	path: &Path,
+	line: usize,
+) -> Result<Operation, TrackerError> {
+	let (dim, rest) = take_token(rest);
+	let (x, rest) = take_token(rest);
+	let (z, rest) = take_token(rest);
+	let dimension = parse_int(dim, path, line, "dimension")?;
+	let x = parse_int(x, path, line, "X coordinate")?;
+	let z = parse_int(z, path, line, "Z coordinate")?;
+	let (field, value, unset) = if command == "set" {
+		let (key, value) = rest.split_once('=').ok_or_else(|| {
+			TrackerError::Message(format!(
+				"{}:{}: set requires a `field=value` assignment",
+				path.display(),
+				line
+			))
+		})?;
+		let field = EditField::parse(key.trim()).ok_or_else(|| {
+			TrackerError::Message(format!(
+				"{}:{}: unknown field `{}`",
+				path.display(),
+				line,
+				key.trim()
+			))
+		})?;
+		(field, value.to_owned(), false)
+	} else {
+		let (name, _) = take_token(rest);
+		let field = EditField::parse(name).ok_or_else(|| {
+			TrackerError::Message(format!(
+				"{}:{}: unknown field `{}`",
+				path.display(),
+				line,
+				name
+			))
+		})?;
+		(field, String::new(), true)
+	};
+	Ok(Operation {
+		dimension,
+		x,
+		z,
+		field,
+		value,
+		unset,
+	})
+}
+
+/// Applies one operation to every matching sample, returning whether a match
+/// of the appropriate mod type was found.
+fn apply_one(samples: &mut [Sample], op: &Operation) -> bool {
+	let mut found = false;
+	for sample in samples.iter_mut() {
+		if sample.dimension != op.dimension || sample.x != op.x || sample.z != op.z {
+			continue;
+		}
+		match (&mut sample.data, op.field) {
+			(SampleData::Immersive(data), EditField::Mineral) => {
+				data.mineral = op.value.clone();
+				found = true;
+			}
+			(SampleData::Immersive(data), EditField::Liquid) => {
+				data.liquid = op.value.clone();
+				found = true;
+			}
+			(
+				SampleData::TerraFirmaCraft(ore) | SampleData::Geolosys(ore),
+				EditField::Ore,
+			) => {
+				*ore = op.value.clone();
+				found = true;
+			}
+			_ => {}
+		}
+	}
+	found
+}
+
+/// Applies all operations to the samples, reporting each directive's outcome
+/// and failing the whole run if any directive matches no sample.
+pub fn apply(samples: &mut [Sample], ops: &[Operation]) -> Result<(), TrackerError> {
+	let mut missing = 0_usize;
+	for op in ops {
+		let verb = if op.unset { "unset" } else { "set" };
+		let found = apply_one(samples, op);
+		if found {
+			println!(
+				"{} dimension {} X={} Z={} {}: updated",
+				verb, op.dimension, op.x, op.z, op.field.name()
+			);
+		} else {
+			missing += 1;
+			println!(
+				"{} dimension {} X={} Z={} {}: no matching sample",
+				verb, op.dimension, op.x, op.z, op.field.name()
+			);
+		}
+	}
+	if missing == 0 {
+		Ok(())
+	} else {
+		Err(TrackerError::Message(format!(
+			"{} directive(s) matched no sample; no changes written",
+			missing
+		)))
+	}
+}